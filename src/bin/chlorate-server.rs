@@ -0,0 +1,21 @@
+//! Out-of-process SODA host. Owns the crash-prone `libsoda` FFI and serves it
+//! over a Unix domain socket so a SODA segfault cannot take down a client.
+//!
+//! Usage: `chlorate-server <socket-path>`
+
+use std::process;
+
+fn main() {
+    let socket_path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: chlorate-server <socket-path>");
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = chlorate::run_server(&socket_path) {
+        eprintln!("chlorate-server: {}", e);
+        process::exit(1);
+    }
+}
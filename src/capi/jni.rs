@@ -0,0 +1,110 @@
+//! Feature-gated JNI entry points over [`SodaBuilder`](crate::SodaBuilder),
+//! built on the same engine as the C ABI. Enable with the `jni` feature.
+//!
+//! The functions back a `com.github.rumblefrog.chlorate.Soda` Java class that
+//! holds the client as an opaque `long` handle. Responses are delivered by
+//! invoking `void onResponse(byte[])` on a callback object, passing the
+//! serialized `SodaResponse` bytes.
+
+use jni::objects::{GlobalRef, JByteArray, JClass, JObject, JString, JValue};
+use jni::sys::jlong;
+use jni::{JNIEnv, JavaVM};
+
+use prost::Message;
+
+use crate::{SodaBuilder, SodaClient, SodaResponse};
+
+/// Owns the session and everything the SODA thread needs to call back into the
+/// JVM.
+struct Session {
+    client: SodaClient<'static>,
+}
+
+/// Starts a session and returns its handle as a `jlong`.
+///
+/// # Safety
+/// Called by the JVM for `Soda.nativeBuild`.
+#[no_mangle]
+pub extern "system" fn Java_com_github_rumblefrog_chlorate_Soda_nativeBuild<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    language_pack_directory: JString<'local>,
+    callback: JObject<'local>,
+) -> jlong {
+    let directory: String = match env.get_string(&language_pack_directory) {
+        Ok(s) => s.into(),
+        Err(_) => return 0,
+    };
+
+    let vm = match env.get_java_vm() {
+        Ok(vm) => vm,
+        Err(_) => return 0,
+    };
+    let callback: GlobalRef = match env.new_global_ref(callback) {
+        Ok(r) => r,
+        Err(_) => return 0,
+    };
+
+    let client = SodaBuilder::new()
+        .language_pack_directory(directory)
+        .build(move |resp| deliver(&vm, &callback, resp));
+
+    Box::into_raw(Box::new(Session { client })) as jlong
+}
+
+/// Delivers one response to the Java callback, attaching the SODA thread to the
+/// JVM for the duration of the call.
+fn deliver(vm: &JavaVM, callback: &GlobalRef, resp: SodaResponse) {
+    let mut env = match vm.attach_current_thread() {
+        Ok(env) => env,
+        Err(_) => return,
+    };
+
+    let bytes = resp.encode_to_vec();
+    if let Ok(array) = env.byte_array_from_slice(&bytes) {
+        let _ = env.call_method(
+            callback.as_obj(),
+            "onResponse",
+            "([B)V",
+            &[JValue::Object(&array)],
+        );
+    }
+}
+
+/// Feeds a chunk of PCM to the session identified by `handle`.
+///
+/// # Safety
+/// Called by the JVM for `Soda.nativeAddAudio`; `handle` must come from
+/// `nativeBuild`.
+#[no_mangle]
+pub extern "system" fn Java_com_github_rumblefrog_chlorate_Soda_nativeAddAudio<'local>(
+    env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+    data: JByteArray<'local>,
+) {
+    if handle == 0 {
+        return;
+    }
+
+    if let Ok(bytes) = env.convert_byte_array(&data) {
+        let session = unsafe { &mut *(handle as *mut Session) };
+        session.client.add_audio(bytes.as_slice());
+    }
+}
+
+/// Releases the session identified by `handle`, stopping SODA.
+///
+/// # Safety
+/// Called by the JVM for `Soda.nativeFree`; `handle` must come from
+/// `nativeBuild` and must not be used again.
+#[no_mangle]
+pub extern "system" fn Java_com_github_rumblefrog_chlorate_Soda_nativeFree<'local>(
+    _env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+) {
+    if handle != 0 {
+        drop(unsafe { Box::from_raw(handle as *mut Session) });
+    }
+}
@@ -0,0 +1,338 @@
+//! Opt-in C ABI over [`SodaBuilder`](crate::SodaBuilder) and
+//! [`SodaClient`](crate::SodaClient).
+//!
+//! chlorate is otherwise only reachable from Rust; this module exports a stable
+//! set of `extern "C"` functions so desktop apps, scripting glue, and other
+//! runtimes can drive the same engine. All lifetime and `Drop` handling stays
+//! in Rust behind opaque pointers: `chlorate_builder_new` hands out a builder,
+//! the `chlorate_builder_*` setters mirror the Rust builder methods,
+//! `chlorate_build` consumes the builder and installs a C callback that
+//! receives serialized `SodaResponse` bytes, `chlorate_add_audio` feeds PCM,
+//! and `chlorate_free` releases the client.
+//!
+//! The feature-gated [`napi`] and [`jni`] submodules build thin language
+//! wrappers on top of the same `SodaBuilder`/`SodaClient`.
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::os::raw::c_void;
+use std::slice;
+
+use prost::Message;
+
+use crate::{RecognitionMode, SampleFormat, SodaBuilder, SodaClient, SodaResponse};
+
+#[cfg(feature = "napi")]
+pub mod napi;
+
+#[cfg(feature = "jni")]
+pub mod jni;
+
+/// A C callback invoked for every response. It receives the serialized
+/// `SodaResponse` bytes, their length, and the opaque user pointer supplied to
+/// [`chlorate_build`].
+pub type ChlorateCallback = extern "C" fn(*const u8, usize, *mut c_void);
+
+/// Wraps the caller's opaque pointer so it can cross the `Send + Sync` bound the
+/// Rust callback requires. The pointer is only handed back to the C callback on
+/// SODA's thread.
+struct CallbackHandle(*mut c_void);
+unsafe impl Send for CallbackHandle {}
+unsafe impl Sync for CallbackHandle {}
+
+/// Reads a borrowed C string into an owned `String`, treating null as empty.
+unsafe fn owned_string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
+
+/// Allocates a new builder. Release it with [`chlorate_builder_free`], or pass
+/// it to [`chlorate_build`], which consumes it.
+#[no_mangle]
+pub extern "C" fn chlorate_builder_new() -> *mut SodaBuilder {
+    Box::into_raw(Box::new(SodaBuilder::new()))
+}
+
+/// Frees a builder that was never passed to [`chlorate_build`].
+///
+/// # Safety
+/// `builder` must be a pointer returned by [`chlorate_builder_new`] that has
+/// not already been freed or consumed.
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_free(builder: *mut SodaBuilder) {
+    if !builder.is_null() {
+        drop(Box::from_raw(builder));
+    }
+}
+
+/// Number of channels in the RAW audio that will be provided to SODA.
+///
+/// # Safety
+/// `builder` must be a live pointer from [`chlorate_builder_new`].
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_channel_count(
+    builder: *mut SodaBuilder,
+    channel_count: u32,
+) {
+    (*builder).channel_count(channel_count);
+}
+
+/// Sample rate SODA is configured to recognize at.
+///
+/// # Safety
+/// See [`chlorate_builder_channel_count`].
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_sample_rate(builder: *mut SodaBuilder, sample_rate: u32) {
+    (*builder).sample_rate(sample_rate);
+}
+
+/// Maximum PipeStream buffer size; 0 means unlimited.
+///
+/// # Safety
+/// See [`chlorate_builder_channel_count`].
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_max_buffer_bytes(
+    builder: *mut SodaBuilder,
+    max_buffer_bytes: u32,
+) {
+    (*builder).max_buffer_bytes(max_buffer_bytes);
+}
+
+/// Forces simulated real-time audio provision (testing only).
+///
+/// # Safety
+/// See [`chlorate_builder_channel_count`].
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_simulate_realtime_testonly(
+    builder: *mut SodaBuilder,
+    simulate: bool,
+) {
+    (*builder).simulate_realtime_testonly(simulate);
+}
+
+/// Directory of the language pack to use. `directory` is a borrowed C string.
+///
+/// # Safety
+/// `builder` must be live and `directory` a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_language_pack_directory(
+    builder: *mut SodaBuilder,
+    directory: *const c_char,
+) {
+    (*builder).language_pack_directory(owned_string(directory));
+}
+
+/// API key used for call verification. `api_key` is a borrowed C string.
+///
+/// # Safety
+/// `builder` must be live and `api_key` a valid nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_api_key(
+    builder: *mut SodaBuilder,
+    api_key: *const c_char,
+) {
+    (*builder).api_key(owned_string(api_key));
+}
+
+/// Recognition mode, matching the `RecognitionMode` proto enum. Unknown values
+/// fall back to the default.
+///
+/// # Safety
+/// See [`chlorate_builder_channel_count`].
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_recognition_mode(builder: *mut SodaBuilder, mode: i32) {
+    let mode = RecognitionMode::try_from(mode).unwrap_or(RecognitionMode::Ime);
+    (*builder).recognition_mode(mode);
+}
+
+/// Whether to force a new session after every final result.
+///
+/// # Safety
+/// See [`chlorate_builder_channel_count`].
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_reset_on_final_result(
+    builder: *mut SodaBuilder,
+    reset: bool,
+) {
+    (*builder).reset_on_final_result(reset);
+}
+
+/// Whether to populate timing metrics on recognition and endpoint events.
+///
+/// # Safety
+/// See [`chlorate_builder_channel_count`].
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_include_timing_metrics(
+    builder: *mut SodaBuilder,
+    include: bool,
+) {
+    (*builder).include_timing_metrics(include);
+}
+
+/// Whether to request lang id events.
+///
+/// # Safety
+/// See [`chlorate_builder_channel_count`].
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_enable_lang_id(builder: *mut SodaBuilder, enable: bool) {
+    (*builder).enable_lang_id(enable);
+}
+
+/// Declares the format of the PCM the caller will feed. `sample_format` is
+/// `0` for f32, `1` for i16, `2` for u16; unknown values are treated as i16.
+///
+/// # Safety
+/// See [`chlorate_builder_channel_count`].
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_input_format(
+    builder: *mut SodaBuilder,
+    channels: u32,
+    sample_rate: u32,
+    sample_format: u32,
+) {
+    let sample_format = match sample_format {
+        0 => SampleFormat::F32,
+        2 => SampleFormat::U16,
+        _ => SampleFormat::I16,
+    };
+    (*builder).input_format(channels, sample_rate, sample_format);
+}
+
+/// Biases recognition toward `count` phrases drawn from the `phrases` array of
+/// C strings, applying `boost` to each.
+///
+/// # Safety
+/// `builder` must be live and `phrases` must point to `count` valid
+/// nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_builder_add_context_phrases(
+    builder: *mut SodaBuilder,
+    phrases: *const *const c_char,
+    count: usize,
+    boost: f32,
+) {
+    let phrases = slice::from_raw_parts(phrases, count)
+        .iter()
+        .map(|&p| owned_string(p))
+        .collect();
+    (*builder).add_context_phrases(phrases, boost);
+}
+
+/// Consumes `builder` and starts a SODA session delivering responses to
+/// `callback`. `user_data` is passed back to the callback untouched. Returns an
+/// opaque client to release with [`chlorate_free`].
+///
+/// # Safety
+/// `builder` must be a live pointer from [`chlorate_builder_new`]; it is
+/// consumed and must not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_build(
+    builder: *mut SodaBuilder,
+    callback: ChlorateCallback,
+    user_data: *mut c_void,
+) -> *mut SodaClient<'static> {
+    let mut builder = Box::from_raw(builder);
+    let handle = CallbackHandle(user_data);
+
+    let client = builder.build(move |resp: SodaResponse| {
+        let bytes = resp.encode_to_vec();
+        callback(bytes.as_ptr(), bytes.len(), handle.0);
+    });
+
+    Box::into_raw(Box::new(client))
+}
+
+/// Feeds `len` bytes of PCM at `data` to the client.
+///
+/// # Safety
+/// `client` must be live and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_add_audio(
+    client: *mut SodaClient<'static>,
+    data: *const u8,
+    len: usize,
+) {
+    let client = &mut *client;
+    client.add_audio(slice::from_raw_parts(data, len));
+}
+
+/// Releases a client returned by [`chlorate_build`], stopping SODA.
+///
+/// # Safety
+/// `client` must be a live pointer from [`chlorate_build`] that has not already
+/// been freed.
+#[no_mangle]
+pub unsafe extern "C" fn chlorate_free(client: *mut SodaClient<'static>) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn add_context_phrases_decodes_pointer_array() {
+        let phrases = ["Blizzy", "rumblefrog"];
+        let cstrings: Vec<CString> = phrases.iter().map(|p| CString::new(*p).unwrap()).collect();
+        let ptrs: Vec<*const c_char> = cstrings.iter().map(|c| c.as_ptr()).collect();
+
+        unsafe {
+            let builder = chlorate_builder_new();
+            chlorate_builder_add_context_phrases(builder, ptrs.as_ptr(), ptrs.len(), 5.0);
+
+            // The decoded phrases must survive all the way into the serialized
+            // config; prost writes the strings verbatim, so they appear in the
+            // encoded bytes.
+            let encoded = (*builder).serialize_config();
+            for phrase in phrases {
+                assert!(
+                    encoded
+                        .windows(phrase.len())
+                        .any(|w| w == phrase.as_bytes()),
+                    "phrase {:?} missing from serialized config",
+                    phrase
+                );
+            }
+
+            chlorate_builder_free(builder);
+        }
+    }
+
+    extern "C" fn count_responses(_data: *const u8, _len: usize, user_data: *mut c_void) {
+        let counter = unsafe { &*(user_data as *const AtomicUsize) };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn build_feed_free_round_trip() {
+        let responses = AtomicUsize::new(0);
+        let model = CString::new("en_models").unwrap();
+        let api_key = CString::new("00000000-0000-0000-0000-000000000000").unwrap();
+
+        unsafe {
+            let builder = chlorate_builder_new();
+            chlorate_builder_language_pack_directory(builder, model.as_ptr());
+            chlorate_builder_api_key(builder, api_key.as_ptr());
+
+            let client = chlorate_build(
+                builder,
+                count_responses,
+                &responses as *const AtomicUsize as *mut c_void,
+            );
+
+            // A short burst of silence exercises the audio path; we only assert
+            // the ABI round trip runs and frees cleanly, not the transcription.
+            let pcm = [0u8; 4096];
+            chlorate_add_audio(client, pcm.as_ptr(), pcm.len());
+
+            chlorate_free(client);
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Feature-gated Node addon over [`SodaBuilder`](crate::SodaBuilder), built on
+//! the same engine as the C ABI. Enable with the `napi` feature.
+
+use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi_derive::napi;
+
+use crate::{SodaBuilder, SodaClient, SodaResponse};
+
+/// A decoded response delivered to JavaScript as a plain object.
+#[napi(object)]
+pub struct JsSodaResponse {
+    /// The `SodaMessageType` discriminant of the response.
+    pub message_type: i32,
+    /// The top recognition hypothesis, when the response carries one.
+    pub transcription: Option<String>,
+}
+
+impl From<SodaResponse> for JsSodaResponse {
+    fn from(resp: SodaResponse) -> JsSodaResponse {
+        let transcription = resp
+            .recognition_result
+            .and_then(|r| r.hypothesis.into_iter().next());
+
+        JsSodaResponse {
+            message_type: resp.soda_message_type,
+            transcription,
+        }
+    }
+}
+
+/// A live SODA session that forwards decoded responses to a JS callback.
+#[napi]
+pub struct Soda {
+    client: SodaClient<'static>,
+}
+
+#[napi]
+impl Soda {
+    /// Starts a session that recognizes from `language_pack_directory` and
+    /// invokes `callback` with each decoded [`JsSodaResponse`].
+    #[napi(constructor)]
+    pub fn new(
+        language_pack_directory: String,
+        callback: ThreadsafeFunction<JsSodaResponse, ErrorStrategy::Fatal>,
+    ) -> Soda {
+        let client = SodaBuilder::new()
+            .language_pack_directory(language_pack_directory)
+            .build(move |resp| {
+                callback.call(resp.into(), ThreadsafeFunctionCallMode::NonBlocking);
+            });
+
+        Soda { client }
+    }
+
+    /// Feeds a chunk of PCM to SODA.
+    #[napi]
+    pub fn add_audio(&mut self, data: Buffer) {
+        self.client.add_audio(data.as_ref());
+    }
+}
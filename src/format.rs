@@ -0,0 +1,276 @@
+//! Conversion of arbitrary PCM into the mono, target-rate, signed-16 format
+//! SODA expects.
+//!
+//! The raw [`add_audio`](crate::SodaClient::add_audio) path slices its input
+//! into chunks verbatim, which only works when the caller already produced
+//! mono 16 kHz `i16`. [`SodaBuilder::input_format`](crate::SodaBuilder::input_format)
+//! records what the caller actually supplies; when that differs from the SODA
+//! target the [`AudioConverter`] downmixes to mono, converts the sample type to
+//! `i16`, and resamples to the target rate, carrying both the trailing partial
+//! input frame and the resampler's fractional position across `Read` chunk
+//! boundaries so the stream stays continuous.
+
+/// Sample type of the PCM a caller feeds to [`SodaClient`](crate::SodaClient).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// 32-bit float in `[-1.0, 1.0]`.
+    F32,
+    /// Signed 16-bit.
+    I16,
+    /// Unsigned 16-bit, silence at 32768.
+    U16,
+}
+
+impl SampleFormat {
+    /// Bytes occupied by one sample on the wire.
+    fn width(self) -> usize {
+        match self {
+            SampleFormat::F32 => 4,
+            SampleFormat::I16 => 2,
+            SampleFormat::U16 => 2,
+        }
+    }
+
+    /// Normalizes one little-endian sample into `[-1.0, 1.0]`.
+    fn to_f32(self, bytes: &[u8]) -> f32 {
+        match self {
+            SampleFormat::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            SampleFormat::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32,
+            SampleFormat::U16 => {
+                (u16::from_le_bytes([bytes[0], bytes[1]]) as f32 - 32768.0) / 32768.0
+            }
+        }
+    }
+}
+
+/// What the caller will actually supply to the client, as recorded by
+/// [`SodaBuilder::input_format`](crate::SodaBuilder::input_format).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct InputFormat {
+    pub channels: u32,
+    pub sample_rate: u32,
+    pub sample_format: SampleFormat,
+}
+
+/// Linearly resamples a mono stream from `device_rate` to `target_rate`,
+/// carrying the fractional sample position across calls so the output stays
+/// continuous across chunk boundaries.
+pub(crate) struct Resampler {
+    ratio: f32,
+    pos: f32,
+    prev: f32,
+}
+
+impl Resampler {
+    pub(crate) fn new(device_rate: u32, target_rate: u32) -> Resampler {
+        Resampler {
+            ratio: device_rate as f32 / target_rate as f32,
+            pos: 0.0,
+            prev: 0.0,
+        }
+    }
+
+    /// Feeds one chunk's worth of mono samples, appending the resampled
+    /// little-endian `i16` output to `out`. Index `-1` of the virtual stream is
+    /// the previous chunk's last sample, so interpolation stays continuous
+    /// across chunk boundaries.
+    pub(crate) fn push(&mut self, mono: &[f32], out: &mut Vec<u8>) {
+        let len = mono.len();
+        if len == 0 {
+            return;
+        }
+
+        let prev = self.prev;
+        let at = |i: isize| -> f32 {
+            if i < 0 {
+                prev
+            } else {
+                mono[i as usize]
+            }
+        };
+
+        // Emit every output sample that can be interpolated from two available
+        // input samples (prev .. mono[len - 1]).
+        while self.pos < (len - 1) as f32 {
+            let idx = self.pos.floor() as isize;
+            let frac = self.pos - idx as f32;
+            let interpolated = at(idx) + (at(idx + 1) - at(idx)) * frac;
+            let clamped = (interpolated.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            out.extend_from_slice(&clamped.to_le_bytes());
+            self.pos += self.ratio;
+        }
+
+        self.prev = mono[len - 1];
+        // Rebase so the last consumed sample becomes index -1 of the next chunk.
+        self.pos -= len as f32;
+    }
+}
+
+/// Streaming converter from the caller's [`InputFormat`] to the SODA target
+/// format. Holds the partial input frame and resampler state so repeated
+/// [`push`](AudioConverter::push) calls over a `Read` stay continuous.
+pub(crate) struct AudioConverter {
+    format: InputFormat,
+    resampler: Resampler,
+    /// Bytes of an input frame that did not complete in the previous chunk.
+    leftover: Vec<u8>,
+    /// Set when the input already matches the target, so bytes pass straight
+    /// through without conversion.
+    passthrough: bool,
+}
+
+impl AudioConverter {
+    pub(crate) fn new(format: InputFormat, target_rate: u32) -> AudioConverter {
+        let passthrough = format.channels == 1
+            && format.sample_rate == target_rate
+            && format.sample_format == SampleFormat::I16;
+
+        AudioConverter {
+            resampler: Resampler::new(format.sample_rate, target_rate),
+            format,
+            leftover: Vec::new(),
+            passthrough,
+        }
+    }
+
+    /// Converts one chunk of raw input bytes, appending mono target-rate `i16`
+    /// bytes to `out`. Bytes that do not complete a whole input frame are held
+    /// back for the next call, so frames are never split mid-sample.
+    pub(crate) fn push(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        if self.passthrough {
+            out.extend_from_slice(input);
+            return;
+        }
+
+        let width = self.format.sample_format.width();
+        let channels = self.format.channels as usize;
+        let frame_bytes = width * channels;
+
+        self.leftover.extend_from_slice(input);
+
+        let n_frames = self.leftover.len() / frame_bytes;
+        let consumed = n_frames * frame_bytes;
+
+        let mut mono = Vec::with_capacity(n_frames);
+        for frame in self.leftover[..consumed].chunks(frame_bytes) {
+            let sum: f32 = frame
+                .chunks(width)
+                .map(|s| self.format.sample_format.to_f32(s))
+                .sum();
+            mono.push(sum / channels as f32);
+        }
+
+        self.leftover.drain(..consumed);
+        self.resampler.push(&mono, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes a little-endian `i16` byte buffer back into samples.
+    fn decode(out: &[u8]) -> Vec<i16> {
+        out.chunks(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect()
+    }
+
+    #[test]
+    fn resampler_unit_ratio_is_continuous() {
+        // At ratio 1.0 the stream reproduces its input in order, and the `-1`
+        // rebase keeps the boundary sample from being dropped or duplicated.
+        let mut r = Resampler::new(16000, 16000);
+        let mut out = Vec::new();
+        r.push(&[0.0, 0.25, 0.5], &mut out);
+        r.push(&[0.75, 1.0, -1.0], &mut out);
+        assert_eq!(
+            decode(&out),
+            vec![0, 8191, 16383, 24575, 32767],
+        );
+    }
+
+    #[test]
+    fn resampler_downsamples_by_two() {
+        let mut r = Resampler::new(32000, 16000);
+        let mut out = Vec::new();
+        r.push(&[0.0, 0.1, 0.2, 0.3], &mut out);
+        // Every other sample survives: index 0 and index 2.
+        assert_eq!(decode(&out), vec![0, (0.2 * i16::MAX as f32) as i16]);
+    }
+
+    #[test]
+    fn resampler_upsamples_with_interpolation() {
+        let mut r = Resampler::new(8000, 16000);
+        let mut out = Vec::new();
+        r.push(&[0.0, 1.0], &mut out);
+        // Emits index 0 and the midpoint 0.5 between the two samples.
+        assert_eq!(decode(&out), vec![0, (0.5 * i16::MAX as f32) as i16]);
+        // Continuing across the boundary interpolates against the carried
+        // previous sample rather than restarting at zero.
+        out.clear();
+        r.push(&[0.0], &mut out);
+        assert_eq!(decode(&out), vec![i16::MAX, (0.5 * i16::MAX as f32) as i16]);
+    }
+
+    #[test]
+    fn converter_splits_frame_across_push() {
+        let format = InputFormat {
+            channels: 2,
+            sample_rate: 16000,
+            sample_format: SampleFormat::I16,
+        };
+
+        // Three stereo frames = 12 bytes. Feeding them all at once must match
+        // feeding them split mid-frame at an odd byte boundary.
+        let mut bytes = Vec::new();
+        for (l, r) in [(100i16, 200i16), (300, 400), (500, 600)] {
+            bytes.extend_from_slice(&l.to_le_bytes());
+            bytes.extend_from_slice(&r.to_le_bytes());
+        }
+
+        let mut whole = AudioConverter::new(format, 16000);
+        let mut whole_out = Vec::new();
+        whole.push(&bytes, &mut whole_out);
+
+        let mut split = AudioConverter::new(format, 16000);
+        let mut split_out = Vec::new();
+        split.push(&bytes[..5], &mut split_out);
+        split.push(&bytes[5..], &mut split_out);
+
+        assert_eq!(split_out, whole_out);
+        assert!(!whole_out.is_empty());
+    }
+
+    #[test]
+    fn converter_scales_f32_and_u16() {
+        let f32_fmt = InputFormat {
+            channels: 1,
+            sample_rate: 16000,
+            sample_format: SampleFormat::F32,
+        };
+        let mut conv = AudioConverter::new(f32_fmt, 16000);
+        let mut out = Vec::new();
+        let mut bytes = Vec::new();
+        for s in [0.0f32, 1.0, -1.0] {
+            bytes.extend_from_slice(&s.to_le_bytes());
+        }
+        conv.push(&bytes, &mut out);
+        assert_eq!(decode(&out), vec![0, i16::MAX]);
+
+        let u16_fmt = InputFormat {
+            channels: 1,
+            sample_rate: 16000,
+            sample_format: SampleFormat::U16,
+        };
+        let mut conv = AudioConverter::new(u16_fmt, 16000);
+        let mut out = Vec::new();
+        let mut bytes = Vec::new();
+        for s in [32768u16, 0, 65535] {
+            bytes.extend_from_slice(&s.to_le_bytes());
+        }
+        conv.push(&bytes, &mut out);
+        // 32768 is silence (0); 0 is full negative.
+        assert_eq!(decode(&out), vec![0, -i16::MAX]);
+    }
+}
@@ -0,0 +1,162 @@
+use std::ffi::c_void;
+use std::marker::PhantomData;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{SampleFormat, StreamConfig};
+
+use crate::format::Resampler;
+use crate::SodaClient;
+
+/// Guard returned by the capture helpers on [`SodaClient`]. Holding it keeps
+/// the underlying cpal input stream running and borrows the client for `'a`,
+/// so the client cannot be dropped (and its SODA handle torn down) while
+/// capture is live; dropping the guard stops the stream.
+pub struct CaptureStream<'a> {
+    _stream: cpal::Stream,
+    _client: PhantomData<&'a mut ()>,
+}
+
+/// Errors raised while opening a capture stream.
+#[derive(Debug)]
+pub enum CaptureError {
+    /// No input device matched the request.
+    NoDevice,
+    /// A device was found but its native sample format is not one we convert.
+    UnsupportedFormat(cpal::SampleFormat),
+    /// The host refused to report a default input configuration.
+    DefaultConfig(cpal::DefaultStreamConfigError),
+    /// The stream could not be built.
+    BuildStream(cpal::BuildStreamError),
+    /// The stream was built but could not be started.
+    PlayStream(cpal::PlayStreamError),
+}
+
+impl std::fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureError::NoDevice => write!(f, "no matching input device"),
+            CaptureError::UnsupportedFormat(fmt) => {
+                write!(f, "unsupported input sample format: {:?}", fmt)
+            }
+            CaptureError::DefaultConfig(e) => write!(f, "default input config: {}", e),
+            CaptureError::BuildStream(e) => write!(f, "build input stream: {}", e),
+            CaptureError::PlayStream(e) => write!(f, "play input stream: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CaptureError {}
+
+// The cpal data callback runs on an audio thread, so it has to be `Send`. The
+// SODA handle is only ever touched through `ExtendedAddAudio`, which is
+// internally synchronized, so sending the raw pointer across is sound.
+struct SodaHandle(*mut c_void);
+unsafe impl Send for SodaHandle {}
+
+impl SodaClient<'_> {
+    /// Opens the host's default input device and streams its audio straight
+    /// into SODA. Returns a [`CaptureStream`] guard that borrows this client
+    /// for as long as capture runs, and stops capture when dropped.
+    pub fn capture_from_default_input(&mut self) -> Result<CaptureStream<'_>, CaptureError> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or(CaptureError::NoDevice)?;
+        self.capture(device)
+    }
+
+    /// Opens the named input device and streams its audio into SODA. Returns a
+    /// [`CaptureStream`] guard that borrows this client for as long as capture
+    /// runs, and stops capture when dropped.
+    pub fn capture_from_device(&mut self, name: &str) -> Result<CaptureStream<'_>, CaptureError> {
+        let device = cpal::default_host()
+            .input_devices()
+            .map_err(|_| CaptureError::NoDevice)?
+            .find(|d| d.name().map(|n| n == name).unwrap_or(false))
+            .ok_or(CaptureError::NoDevice)?;
+        self.capture(device)
+    }
+
+    fn capture(&mut self, device: cpal::Device) -> Result<CaptureStream<'_>, CaptureError> {
+        let supported = device
+            .default_input_config()
+            .map_err(CaptureError::DefaultConfig)?;
+
+        let sample_format = supported.sample_format();
+        let config: StreamConfig = supported.into();
+        let channels = config.channels as usize;
+        let device_rate = config.sample_rate.0;
+
+        let handle = SodaHandle(self.soda_handle);
+        let mut resampler = Resampler::new(device_rate, self.target_rate);
+
+        let err_fn = |err| eprintln!("input stream error: {}", err);
+
+        let stream = match sample_format {
+            SampleFormat::F32 => device.build_input_stream(
+                &config,
+                move |data: &[f32], _| {
+                    let mono = downmix(data, channels, |s| s);
+                    feed(&handle, &mut resampler, &mono);
+                },
+                err_fn,
+                None,
+            ),
+            SampleFormat::I16 => device.build_input_stream(
+                &config,
+                move |data: &[i16], _| {
+                    let mono = downmix(data, channels, |s| s as f32 / i16::MAX as f32);
+                    feed(&handle, &mut resampler, &mono);
+                },
+                err_fn,
+                None,
+            ),
+            SampleFormat::U16 => device.build_input_stream(
+                &config,
+                move |data: &[u16], _| {
+                    let mono = downmix(data, channels, |s| (s as f32 - 32768.0) / 32768.0);
+                    feed(&handle, &mut resampler, &mono);
+                },
+                err_fn,
+                None,
+            ),
+            other => return Err(CaptureError::UnsupportedFormat(other)),
+        }
+        .map_err(CaptureError::BuildStream)?;
+
+        stream.play().map_err(CaptureError::PlayStream)?;
+
+        Ok(CaptureStream {
+            _stream: stream,
+            _client: PhantomData,
+        })
+    }
+}
+
+/// Averages interleaved channels down to a mono buffer, normalizing each
+/// source sample into `[-1, 1]` via `to_f32`.
+fn downmix<T: Copy>(data: &[T], channels: usize, to_f32: impl Fn(T) -> f32) -> Vec<f32> {
+    if channels <= 1 {
+        return data.iter().map(|&s| to_f32(s)).collect();
+    }
+
+    data.chunks(channels)
+        .map(|frame| frame.iter().map(|&s| to_f32(s)).sum::<f32>() / channels as f32)
+        .collect()
+}
+
+fn feed(handle: &SodaHandle, resampler: &mut Resampler, mono: &[f32]) {
+    let mut out = Vec::new();
+    resampler.push(mono, &mut out);
+
+    if out.is_empty() {
+        return;
+    }
+
+    unsafe {
+        crate::ExtendedAddAudio(
+            handle.0,
+            out.as_ptr() as *const libc::c_char,
+            out.len() as libc::c_int,
+        )
+    };
+}
@@ -5,13 +5,32 @@ use std::ops::Drop;
 
 use libc::{c_char, c_int, c_void};
 
+use format::{AudioConverter, InputFormat};
+
 use prost::Message;
 
 mod soda_api {
     include!(concat!(env!("OUT_DIR"), "/speech.soda.api.rs"));
 }
 
-use soda_api::SerializedSodaConfigMsg;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod format;
+mod input;
+mod remote;
+pub mod rpc;
+mod server;
+#[cfg(feature = "shm")]
+pub mod shm;
+mod stream;
+
+pub use format::SampleFormat;
+pub use input::{CaptureError, CaptureStream};
+pub use remote::RemoteSodaClient;
+pub use server::run_server;
+pub use stream::SodaResponseStream;
+
+use soda_api::{SerializedSodaConfigMsg, SpeechContext, SpeechRecognitionContext};
 
 pub use soda_api::{
     serialized_soda_config_msg::RecognitionMode,
@@ -78,6 +97,15 @@ pub struct SodaBuilder {
     include_timing_metrics: bool,
 
     enable_lang_id: bool,
+
+    /// Format of the PCM the caller will feed in, when it differs from the SODA
+    /// target. `None` means the caller already supplies mono target-rate `i16`.
+    input_format: Option<InputFormat>,
+
+    /// Phrases recognition should be biased toward, with their shared boost.
+    context_phrases: Vec<String>,
+
+    context_boost: f32,
 }
 
 impl Default for SodaBuilder {
@@ -93,6 +121,9 @@ impl Default for SodaBuilder {
             reset_on_final_result: true,
             include_timing_metrics: true,
             enable_lang_id: false,
+            input_format: None,
+            context_phrases: Vec::new(),
+            context_boost: 0.0,
         }
     }
 }
@@ -178,12 +209,58 @@ impl SodaBuilder {
         self
     }
 
-    /// Consumes `SodaBuilder` to create `SodaClient`.
-    pub fn build<'soda>(
+    /// Describes the PCM the caller will actually supply to `add_audio`.
+    ///
+    /// SODA wants mono signed-16 PCM at [`sample_rate`](SodaBuilder::sample_rate).
+    /// When the supplied audio differs — a different sample type, channel count,
+    /// or rate — `add_audio` downmixes to mono, converts samples to `i16`, and
+    /// linearly resamples to the target rate instead of slicing the bytes
+    /// through unchanged. Leave unset when already feeding mono target-rate
+    /// `i16`.
+    pub fn input_format(
         &mut self,
-        callback: impl Fn(SodaResponse) + Send + Sync + 'soda,
-    ) -> SodaClient<'soda> {
-        let callback: SodaCallback = Box::new(Box::new(callback));
+        channels: u32,
+        sample_rate: u32,
+        sample_format: SampleFormat,
+    ) -> &mut SodaBuilder {
+        self.input_format = Some(InputFormat {
+            channels,
+            sample_rate,
+            sample_format,
+        });
+        self
+    }
+
+    /// Biases recognition toward `phrases`, applying `boost` to each. Useful
+    /// for steering transcription toward domain terms, names, or commands — a
+    /// dictation app might load a user's contact list here. Repeated calls
+    /// accumulate phrases; the most recent `boost` wins.
+    pub fn add_context_phrases(&mut self, phrases: Vec<String>, boost: f32) -> &mut SodaBuilder {
+        self.context_phrases.extend(phrases);
+        self.context_boost = boost;
+        self
+    }
+
+    /// Clears any phrases previously added with
+    /// [`add_context_phrases`](SodaBuilder::add_context_phrases).
+    pub fn clear_context_phrases(&mut self) -> &mut SodaBuilder {
+        self.context_phrases.clear();
+        self
+    }
+
+    /// Encodes the builder's fields into a serialized `SerializedSodaConfigMsg`,
+    /// the form both the in-process and out-of-process paths hand to SODA.
+    pub(crate) fn serialize_config(&self) -> Vec<u8> {
+        let speech_recognition_context = if self.context_phrases.is_empty() {
+            None
+        } else {
+            Some(SpeechRecognitionContext {
+                context: vec![SpeechContext {
+                    phrases: self.context_phrases.clone(),
+                    boost: Some(self.context_boost),
+                }],
+            })
+        };
 
         let config = SerializedSodaConfigMsg {
             channel_count: Some(self.channel_count as i32),
@@ -196,18 +273,29 @@ impl SodaBuilder {
             reset_on_final_result: Some(self.reset_on_final_result),
             include_timing_metrics: Some(self.include_timing_metrics),
             enable_lang_id: Some(self.enable_lang_id),
+            speech_recognition_context,
             ..Default::default()
         };
 
-        let mut buf = Vec::new();
+        config.encode_to_vec()
+    }
+
+    /// Consumes `SodaBuilder` to create `SodaClient`.
+    pub fn build<'soda>(
+        &mut self,
+        callback: impl Fn(SodaResponse) + Send + Sync + 'soda,
+    ) -> SodaClient<'soda> {
+        let callback: SodaCallback = Box::new(Box::new(callback));
+
+        let buf = self.serialize_config();
 
-        config.encode(&mut buf).unwrap();
+        let callback_handle = Box::into_raw(callback) as *mut c_void;
 
         let serialized = SerializedSodaConfig {
             soda_config: buf.as_ptr() as *const c_char,
             soda_config_size: buf.len() as i32,
             callback: soda_callback,
-            callback_handle: Box::into_raw(callback) as *mut c_void,
+            callback_handle,
         };
 
         let p = unsafe {
@@ -220,14 +308,57 @@ impl SodaBuilder {
 
         SodaClient {
             soda_handle: p,
+            callback_handle,
+            stream_sender: None,
+            target_rate: self.sample_rate,
+            input_format: self.input_format,
             phantom: PhantomData,
         }
     }
+
+    /// Consumes `SodaBuilder` to create a `SodaClient` whose responses are
+    /// delivered through a [`SodaResponseStream`] rather than a closure.
+    ///
+    /// The returned stream yields `SodaResponse`s as SODA produces them and
+    /// closes once the client is dropped, which makes it natural to drive from
+    /// an async runtime with `while let Some(resp) = stream.next().await`.
+    pub fn build_stream(&mut self) -> (SodaClient<'static>, SodaResponseStream) {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+
+        // The closure handed to `build` only ever sends on this clone; the
+        // original `tx` is kept on the client so `Drop` can close the channel
+        // independently of the leaked callback box (see `SodaClient::drop`).
+        let sender = tx.clone();
+
+        let mut client = self.build(move |resp| {
+            // Ignore send errors: a dropped receiver just means the consumer
+            // stopped caring about results.
+            let _ = tx.unbounded_send(resp);
+        });
+        client.stream_sender = Some(sender);
+
+        (client, SodaResponseStream::new(rx))
+    }
 }
 
 pub struct SodaClient<'soda> {
     soda_handle: *mut c_void,
 
+    /// Raw pointer to the boxed user callback. Deliberately leaked rather than
+    /// reclaimed in `Drop` — see the note there.
+    callback_handle: *mut c_void,
+
+    /// Sending half of the `build_stream` channel, kept only so `Drop` can
+    /// close the channel; `None` for clients built via `build`.
+    stream_sender: Option<futures::channel::mpsc::UnboundedSender<SodaResponse>>,
+
+    /// Sample rate SODA was configured with; capture/conversion paths resample
+    /// incoming audio to this rate.
+    target_rate: u32,
+
+    /// Format of incoming PCM, when it needs converting to the SODA target.
+    input_format: Option<InputFormat>,
+
     phantom: PhantomData<&'soda ()>,
 }
 
@@ -255,33 +386,89 @@ impl<'soda> SodaClient<'soda> {
     {
         let mut data = data;
 
+        // When an input format was declared, convert each read into mono
+        // target-rate `i16`; otherwise bytes pass through unchanged. The
+        // converter carries its partial frame and resampler position across
+        // reads, so the feed stays continuous and frame-aligned.
+        let mut converter = self
+            .input_format
+            .map(|format| AudioConverter::new(format, self.target_rate));
+
         let mut chunk = vec![0; 2048];
+        let mut out = Vec::new();
 
         while let Ok(len) = data.read(&mut chunk) {
             if len == 0 {
                 break;
             }
 
-            unsafe {
-                ExtendedAddAudio(
-                    self.soda_handle,
-                    (&chunk[..len]).as_ptr() as *const c_char,
-                    len as c_int,
-                )
-            };
-
-            // Sleep for 20ms to simulate real-time audio. SODA requires audio
-            // streaming in order to return events.
-            if simulate_real_time {
-                std::thread::sleep(std::time::Duration::from_millis(20));
+            match converter.as_mut() {
+                Some(converter) => converter.push(&chunk[..len], &mut out),
+                None => out.extend_from_slice(&chunk[..len]),
             }
+
+            // Feed whole 2048-byte chunks, keeping any remainder for the next
+            // read so we never split a sample across calls.
+            let mut off = 0;
+            while out.len() - off >= 2048 {
+                self.send_audio(&out[off..off + 2048], simulate_real_time);
+                off += 2048;
+            }
+            out.drain(..off);
+        }
+
+        // Flush the final short chunk.
+        if !out.is_empty() {
+            self.send_audio(&out, simulate_real_time);
+        }
+    }
+
+    /// Hands one chunk of mono `i16` PCM to SODA, optionally pacing it to
+    /// real time.
+    fn send_audio(&mut self, chunk: &[u8], simulate_real_time: bool) {
+        unsafe {
+            ExtendedAddAudio(
+                self.soda_handle,
+                chunk.as_ptr() as *const c_char,
+                chunk.len() as c_int,
+            )
+        };
+
+        // Sleep for 20ms to simulate real-time audio. SODA requires audio
+        // streaming in order to return events.
+        if simulate_real_time {
+            std::thread::sleep(std::time::Duration::from_millis(20));
         }
     }
 }
 
 impl<'soda> Drop for SodaClient<'soda> {
     fn drop(&mut self) {
-        unsafe { DeleteExtendedSodaAsync(self.soda_handle) };
+        unsafe {
+            DeleteExtendedSodaAsync(self.soda_handle);
+        };
+
+        // Close the stream half of `build_stream` explicitly: the callback
+        // box below is leaked, so nothing else would ever drop the `Sender`
+        // and `stream.next().await` would hang forever instead of yielding
+        // `None`. `connect_stream` doesn't need this — there the closure
+        // itself owns the only `Sender`, and it is dropped when the reader
+        // thread exits on socket close.
+        if let Some(tx) = self.stream_sender.take() {
+            tx.close_channel();
+        }
+
+        // The callback box is intentionally leaked. `DeleteExtendedSodaAsync`
+        // tears SODA down *asynchronously* and offers no guarantee that its
+        // worker thread has stopped — or will never again invoke the callback —
+        // by the time it returns. Freeing the box here would therefore risk a
+        // use-after-free on the audio thread, and closing its channel `Sender`
+        // (if any) out from under it would corrupt live channel state. Until
+        // Delete is proven to join its worker, leaking the box is the only
+        // sound choice; the handle is a single small allocation per client.
+        // Read the field here so its role as the deliberately-abandoned
+        // handle stays explicit.
+        let _ = self.callback_handle;
     }
 }
 
@@ -0,0 +1,152 @@
+use std::io::{self, Read};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::thread;
+
+use prost::Message;
+
+use crate::rpc::{self, RpcFrame, RpcPayload};
+use crate::stream::SodaResponseStream;
+use crate::{SodaBuilder, SodaResponse};
+
+/// An in-process client that drives an out-of-process SODA host over a Unix
+/// domain socket.
+///
+/// The native `libsoda` blob is large and crash-prone; running it behind a
+/// socket in the `chlorate-server` process means a SODA segfault can no longer
+/// take down the host application. [`RemoteSodaClient::add_audio`] frames PCM
+/// chunks to the server and responses surface through the same callback/stream
+/// API as the in-process [`SodaClient`](crate::SodaClient).
+pub struct RemoteSodaClient {
+    stream: UnixStream,
+
+    /// Shared-memory PCM ring negotiated at connect time when the `shm` feature
+    /// is enabled; `add_audio` writes into it instead of framing each chunk.
+    #[cfg(feature = "shm")]
+    shm: Option<crate::shm::SharedRegion>,
+}
+
+impl SodaBuilder {
+    /// Connects to a `chlorate-server` listening on `socket_path` and delivers
+    /// responses through `callback`.
+    pub fn connect<P: AsRef<Path>>(
+        &mut self,
+        socket_path: P,
+        callback: impl Fn(SodaResponse) + Send + Sync + 'static,
+    ) -> io::Result<RemoteSodaClient> {
+        let (client, reader) = self.connect_inner(socket_path)?;
+
+        thread::spawn(move || {
+            let mut reader = reader;
+            while let Ok(Some(frame)) = rpc::read_frame(&mut reader) {
+                if let Some(RpcPayload::Response(bytes)) = frame.payload {
+                    if let Ok(resp) = SodaResponse::decode(bytes.as_slice()) {
+                        callback(resp);
+                    }
+                }
+            }
+        });
+
+        Ok(client)
+    }
+
+    /// Connects to a `chlorate-server` and surfaces responses through a
+    /// [`SodaResponseStream`], mirroring [`SodaBuilder::build_stream`].
+    pub fn connect_stream<P: AsRef<Path>>(
+        &mut self,
+        socket_path: P,
+    ) -> io::Result<(RemoteSodaClient, SodaResponseStream)> {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+
+        let client = self.connect(socket_path, move |resp| {
+            let _ = tx.unbounded_send(resp);
+        })?;
+
+        Ok((client, SodaResponseStream::new(rx)))
+    }
+
+    /// Opens the socket, sends the serialized config, and hands back the write
+    /// half plus a cloned read half for the caller's reader loop.
+    fn connect_inner<P: AsRef<Path>>(
+        &mut self,
+        socket_path: P,
+    ) -> io::Result<(RemoteSodaClient, UnixStream)> {
+        let mut stream = UnixStream::connect(socket_path)?;
+
+        rpc::write_frame(&mut stream, &RpcFrame::config(self.serialize_config()))?;
+
+        // When the `shm` feature is on, set up a shared-memory PCM ring and tell
+        // the server where to map it. The config frame above has already been
+        // sent, so the server creates its SODA session before it receives this.
+        #[cfg(feature = "shm")]
+        let shm = {
+            let region =
+                crate::shm::SharedRegion::create(&crate::shm::unique_name(), crate::shm::DEFAULT_RING_BYTES)?;
+            rpc::write_frame(
+                &mut stream,
+                &RpcFrame::shm(crate::rpc::ShmRegion {
+                    path: region.name().to_string(),
+                    len: region.len() as u32,
+                }),
+            )?;
+            Some(region)
+        };
+
+        let reader = stream.try_clone()?;
+
+        Ok((
+            RemoteSodaClient {
+                stream,
+                #[cfg(feature = "shm")]
+                shm,
+            },
+            reader,
+        ))
+    }
+}
+
+impl RemoteSodaClient {
+    /// Frames audio to the server in 2048 byte chunks, matching the in-process
+    /// [`SodaClient::add_audio`](crate::SodaClient::add_audio). When a
+    /// shared-memory ring was negotiated, the chunks are pushed into the ring
+    /// instead of being framed over the socket.
+    pub fn add_audio<R: Read>(&mut self, mut data: R) -> io::Result<()> {
+        let mut chunk = vec![0; 2048];
+
+        loop {
+            let len = data.read(&mut chunk)?;
+            if len == 0 {
+                break;
+            }
+
+            #[cfg(feature = "shm")]
+            if let Some(region) = self.shm.as_ref() {
+                // Push into the shared ring, spinning briefly if the consumer
+                // has not yet drained enough room for the whole chunk.
+                let ring = region.ring();
+                let mut off = 0;
+                while off < len {
+                    let n = ring.push(&chunk[off..len]);
+                    off += n;
+                    if n == 0 {
+                        std::thread::sleep(std::time::Duration::from_micros(100));
+                    }
+                }
+                continue;
+            }
+
+            rpc::write_frame(&mut self.stream, &RpcFrame::audio(chunk[..len].to_vec()))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for RemoteSodaClient {
+    fn drop(&mut self) {
+        // Shutting down both directions ends the server's read loop, and our
+        // reader thread sees end of stream on its cloned handle and exits.
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
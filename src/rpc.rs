@@ -0,0 +1,106 @@
+//! Framed RPC shared by [`RemoteSodaClient`](crate::RemoteSodaClient) and the
+//! `chlorate-server` binary.
+//!
+//! Every message on the wire is a 4-byte little-endian length prefix followed
+//! by a protobuf-encoded [`RpcFrame`]. The frame carries one of four
+//! payloads: the already-serialized `SerializedSodaConfigMsg`, a raw PCM audio
+//! chunk, the `SodaResponse` bytes the SODA callback produced, forwarded
+//! verbatim, or the location of a shared-memory PCM ring ([`ShmRegion`]).
+
+use std::io::{self, Read, Write};
+
+use prost::Message;
+
+/// A single RPC message exchanged between client and server.
+#[derive(Clone, PartialEq, Message)]
+pub struct RpcFrame {
+    #[prost(oneof = "RpcPayload", tags = "1, 2, 3, 4")]
+    pub payload: Option<RpcPayload>,
+}
+
+/// Location of a shared-memory PCM ring, negotiated once per session when the
+/// `shm` feature is enabled. After this frame the client writes audio into the
+/// mapped ring instead of sending [`RpcPayload::Audio`] frames, so the PCM path
+/// no longer costs a syscall per chunk.
+#[derive(Clone, PartialEq, Message)]
+pub struct ShmRegion {
+    /// POSIX shared-memory object name (as passed to `shm_open`).
+    #[prost(string, tag = "1")]
+    pub path: String,
+
+    /// Total size of the mapped region in bytes, including the ring header.
+    #[prost(uint32, tag = "2")]
+    pub len: u32,
+}
+
+/// The four kinds of payload a frame can carry.
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum RpcPayload {
+    /// Serialized `SerializedSodaConfigMsg`, sent once when the session opens.
+    #[prost(bytes, tag = "1")]
+    Config(Vec<u8>),
+
+    /// A raw PCM chunk, client to server.
+    #[prost(bytes, tag = "2")]
+    Audio(Vec<u8>),
+
+    /// Serialized `SodaResponse` bytes, server to client, forwarded verbatim.
+    #[prost(bytes, tag = "3")]
+    Response(Vec<u8>),
+
+    /// Negotiates a shared-memory PCM ring for the session, client to server.
+    #[prost(message, tag = "4")]
+    Shm(ShmRegion),
+}
+
+impl RpcFrame {
+    pub fn config(bytes: Vec<u8>) -> RpcFrame {
+        RpcFrame {
+            payload: Some(RpcPayload::Config(bytes)),
+        }
+    }
+
+    pub fn audio(bytes: Vec<u8>) -> RpcFrame {
+        RpcFrame {
+            payload: Some(RpcPayload::Audio(bytes)),
+        }
+    }
+
+    pub fn response(bytes: Vec<u8>) -> RpcFrame {
+        RpcFrame {
+            payload: Some(RpcPayload::Response(bytes)),
+        }
+    }
+
+    pub fn shm(region: ShmRegion) -> RpcFrame {
+        RpcFrame {
+            payload: Some(RpcPayload::Shm(region)),
+        }
+    }
+}
+
+/// Writes a length-prefixed, protobuf-encoded frame to `w`.
+pub fn write_frame<W: Write>(w: &mut W, frame: &RpcFrame) -> io::Result<()> {
+    let body = frame.encode_to_vec();
+    w.write_all(&(body.len() as u32).to_le_bytes())?;
+    w.write_all(&body)?;
+    w.flush()
+}
+
+/// Reads a single length-prefixed frame from `r`, returning `Ok(None)` on a
+/// clean end of stream.
+pub fn read_frame<R: Read>(r: &mut R) -> io::Result<Option<RpcFrame>> {
+    let mut len = [0u8; 4];
+    match r.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let mut body = vec![0u8; u32::from_le_bytes(len) as usize];
+    r.read_exact(&mut body)?;
+
+    RpcFrame::decode(body.as_slice())
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
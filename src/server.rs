@@ -0,0 +1,180 @@
+//! Out-of-process SODA host.
+//!
+//! [`run_server`] owns the `libsoda` FFI and exposes it over a Unix domain
+//! socket using the [`crate::rpc`] framing. The `chlorate-server` binary is a
+//! thin wrapper around it. Because the native blob lives in this process, a
+//! SODA crash stays contained here instead of taking down a client.
+
+use std::ffi::{c_void, CStr};
+use std::io;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use libc::{c_char, c_int};
+
+use crate::rpc::{self, RpcFrame, RpcPayload};
+use crate::SerializedSodaConfig;
+use crate::{CreateExtendedSodaAsync, DeleteExtendedSodaAsync, ExtendedAddAudio, ExtendedSodaStart};
+
+// Shared write half: SODA invokes the callback from its own thread while the
+// connection thread forwards audio, so the socket is guarded by a mutex.
+type ForwardHandle = Arc<Mutex<UnixStream>>;
+
+/// Binds `socket_path` and serves each incoming connection on its own thread,
+/// one SODA session per connection.
+pub fn run_server<P: AsRef<Path>>(socket_path: P) -> io::Result<()> {
+    // A stale socket file from a previous run would block `bind`.
+    let _ = std::fs::remove_file(&socket_path);
+
+    let listener = UnixListener::bind(socket_path)?;
+
+    for conn in listener.incoming() {
+        let conn = conn?;
+        thread::spawn(move || {
+            if let Err(e) = serve_connection(conn) {
+                eprintln!("connection error: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+// Raw SODA handle carried onto the shared-memory consumer thread. The handle
+// is only ever touched through the internally-synchronized `ExtendedAddAudio`.
+#[cfg(feature = "shm")]
+struct SodaHandle(*mut c_void);
+#[cfg(feature = "shm")]
+unsafe impl Send for SodaHandle {}
+
+fn serve_connection(mut stream: UnixStream) -> io::Result<()> {
+    let writer: ForwardHandle = Arc::new(Mutex::new(stream.try_clone()?));
+
+    let mut soda_handle: Option<*mut c_void> = None;
+    let mut callback_handle: *mut ForwardHandle = ptr::null_mut();
+
+    // Shared-memory PCM consumer, spun up if the client negotiates a ring.
+    #[cfg(feature = "shm")]
+    let mut shm_consumer: Option<(
+        Arc<std::sync::atomic::AtomicBool>,
+        thread::JoinHandle<()>,
+    )> = None;
+
+    while let Some(frame) = rpc::read_frame(&mut stream)? {
+        match frame.payload {
+            Some(RpcPayload::Config(bytes)) => {
+                callback_handle = Box::into_raw(Box::new(Arc::clone(&writer)));
+
+                let serialized = SerializedSodaConfig {
+                    soda_config: bytes.as_ptr() as *const c_char,
+                    soda_config_size: bytes.len() as c_int,
+                    callback: forward_callback,
+                    callback_handle: callback_handle as *mut c_void,
+                };
+
+                soda_handle = Some(unsafe {
+                    let handle = CreateExtendedSodaAsync(serialized);
+                    ExtendedSodaStart(handle);
+                    handle
+                });
+            }
+            Some(RpcPayload::Audio(bytes)) => {
+                if let Some(handle) = soda_handle {
+                    unsafe {
+                        ExtendedAddAudio(
+                            handle,
+                            bytes.as_ptr() as *const c_char,
+                            bytes.len() as c_int,
+                        )
+                    };
+                }
+            }
+            #[cfg(feature = "shm")]
+            Some(RpcPayload::Shm(region)) => {
+                if let Some(handle) = soda_handle {
+                    shm_consumer = Some(spawn_shm_consumer(region, handle)?);
+                }
+            }
+            // Servers never receive `Response` frames.
+            _ => {}
+        }
+    }
+
+    // The client hung up: stop the PCM consumer before tearing SODA down.
+    #[cfg(feature = "shm")]
+    if let Some((stop, thread)) = shm_consumer {
+        stop.store(true, std::sync::atomic::Ordering::Release);
+        let _ = thread.join();
+    }
+
+    if let Some(handle) = soda_handle {
+        unsafe { DeleteExtendedSodaAsync(handle) };
+    }
+
+    // The callback box is intentionally leaked, as in `SodaClient::drop`
+    // (src/lib.rs): `DeleteExtendedSodaAsync` tears SODA down asynchronously
+    // with no guarantee its worker thread has stopped calling `forward_callback`
+    // by the time it returns, so freeing the box here would risk a
+    // use-after-free on that thread. Read the pointer so its role as the
+    // deliberately-abandoned handle stays explicit.
+    let _ = callback_handle;
+
+    Ok(())
+}
+
+/// Opens the shared-memory ring the client negotiated and spawns a thread that
+/// drains PCM from it straight into SODA, so the audio path costs no per-chunk
+/// socket syscall. Returns a stop flag and the thread handle so the caller can
+/// shut it down when the connection ends.
+#[cfg(feature = "shm")]
+fn spawn_shm_consumer(
+    region: crate::rpc::ShmRegion,
+    handle: *mut c_void,
+) -> io::Result<(Arc<std::sync::atomic::AtomicBool>, thread::JoinHandle<()>)> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let shared = crate::shm::SharedRegion::open(&region.path, region.len as usize)?;
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let handle = SodaHandle(handle);
+    let thread = {
+        let stop = Arc::clone(&stop);
+        thread::spawn(move || {
+            let ring = shared.ring();
+            let mut buf = vec![0u8; 2048];
+
+            loop {
+                let n = ring.pop(&mut buf);
+                if n == 0 {
+                    // Drain any tail once the client has disconnected.
+                    if stop.load(Ordering::Acquire) {
+                        break;
+                    }
+                    thread::sleep(std::time::Duration::from_micros(100));
+                    continue;
+                }
+
+                unsafe {
+                    ExtendedAddAudio(handle.0, buf.as_ptr() as *const c_char, n as c_int);
+                }
+            }
+        })
+    };
+
+    Ok((stop, thread))
+}
+
+/// Forwards the serialized `SodaResponse` bytes back to the client verbatim,
+/// reading them exactly as the in-process `soda_callback` does.
+extern "C" fn forward_callback(message: *const c_char, _length: c_int, handle: *mut c_void) {
+    let bytes = unsafe { CStr::from_ptr(message) }.to_bytes().to_vec();
+
+    let writer = unsafe { &*(handle as *mut ForwardHandle) };
+
+    if let Ok(mut socket) = writer.lock() {
+        let _ = rpc::write_frame(&mut *socket, &RpcFrame::response(bytes));
+    }
+}
@@ -0,0 +1,290 @@
+//! Optional shared-memory ring buffer for the PCM path.
+//!
+//! For large audio sessions, framing every chunk over the socket means a
+//! `write`/`read` syscall pair per 2048 bytes. When the `shm` feature is
+//! enabled, the client and `chlorate-server` can instead share a single
+//! mapped region carrying a single-producer/single-consumer ring buffer: the
+//! client writes PCM into it lock-free and only the small control frames still
+//! travel over the socket.
+//!
+//! The buffer is laid out as `[head: u32][tail: u32][data: capacity bytes]`
+//! inside a caller-supplied region (typically a `memfd`/`shm_open` mapping),
+//! so the type is agnostic to how the memory is shared.
+
+use std::ffi::CString;
+use std::io;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+/// A single-producer/single-consumer byte ring over a shared region.
+///
+/// `region` must outlive the `Ring` and be identically sized and mapped in
+/// both processes. The two [`AtomicU32`] cursors live at the front of the
+/// region; the remainder is the data area.
+pub struct Ring {
+    head: *const AtomicU32,
+    tail: *const AtomicU32,
+    data: *mut u8,
+    capacity: usize,
+}
+
+// Access is synchronized through the atomic cursors, so the handle is safe to
+// move to the producer/consumer thread.
+unsafe impl Send for Ring {}
+
+const HEADER: usize = 2 * std::mem::size_of::<u32>();
+
+impl Ring {
+    /// Wraps a shared `region`. The region must be at least `HEADER + 1` bytes;
+    /// the data area is `region.len() - HEADER` bytes, of which one is always
+    /// kept empty, so at most `region.len() - HEADER - 1` bytes are buffered.
+    ///
+    /// # Safety
+    ///
+    /// `region` must point to a live mapping of `len` bytes that stays valid
+    /// for the lifetime of the `Ring`, and at most one `Ring` per role
+    /// (producer, consumer) may exist over it.
+    pub unsafe fn new(region: *mut u8, len: usize) -> Ring {
+        debug_assert!(len > HEADER);
+        // The cursor wrap in `push`/`pop` relies on `capacity` dividing 2^usize,
+        // so the data area must be a power of two.
+        debug_assert!((len - HEADER).is_power_of_two());
+
+        Ring {
+            head: region as *const AtomicU32,
+            tail: region.add(std::mem::size_of::<u32>()) as *const AtomicU32,
+            data: region.add(HEADER),
+            capacity: len - HEADER,
+        }
+    }
+
+    fn head(&self) -> &AtomicU32 {
+        unsafe { &*self.head }
+    }
+
+    fn tail(&self) -> &AtomicU32 {
+        unsafe { &*self.tail }
+    }
+
+    /// Producer side: copies as much of `buf` as fits, returning the number of
+    /// bytes written.
+    pub fn push(&self, buf: &[u8]) -> usize {
+        let head = self.head().load(Ordering::Acquire) as usize;
+        let tail = self.tail().load(Ordering::Relaxed) as usize;
+
+        // One slot is kept empty to distinguish full from empty.
+        let free = (self.capacity - 1) - tail.wrapping_sub(head) % self.capacity;
+        let n = buf.len().min(free);
+
+        for (i, &byte) in buf[..n].iter().enumerate() {
+            let at = (tail + i) % self.capacity;
+            unsafe { *self.data.add(at) = byte };
+        }
+
+        self.tail()
+            .store(((tail + n) % self.capacity) as u32, Ordering::Release);
+        n
+    }
+
+    /// Consumer side: drains up to `buf.len()` bytes, returning how many were
+    /// read.
+    pub fn pop(&self, buf: &mut [u8]) -> usize {
+        let tail = self.tail().load(Ordering::Acquire) as usize;
+        let head = self.head().load(Ordering::Relaxed) as usize;
+
+        let available = tail.wrapping_sub(head) % self.capacity;
+        let n = buf.len().min(available);
+
+        for (i, slot) in buf[..n].iter_mut().enumerate() {
+            let at = (head + i) % self.capacity;
+            *slot = unsafe { *self.data.add(at) };
+        }
+
+        self.head()
+            .store(((head + n) % self.capacity) as u32, Ordering::Release);
+        n
+    }
+}
+
+/// Data capacity of the PCM ring negotiated by [`SharedRegion::create`], chosen
+/// so a large audio session rarely fills the buffer between consumer polls. A
+/// power of two, as [`Ring`]'s cursor wrap requires.
+const RING_DATA_BYTES: usize = 1 << 20;
+
+/// Total shared-region size for the default ring: the [`Ring`] header plus
+/// [`RING_DATA_BYTES`] of data.
+pub const DEFAULT_RING_BYTES: usize = HEADER + RING_DATA_BYTES;
+
+/// A POSIX shared-memory object mapped into the process, carrying a [`Ring`] in
+/// its bytes. The client creates one with [`create`](SharedRegion::create) and
+/// hands the server its name over the socket; the server reopens it with
+/// [`open`](SharedRegion::open). The creator unlinks the object on drop.
+pub struct SharedRegion {
+    ptr: *mut u8,
+    len: usize,
+    fd: libc::c_int,
+    name: CString,
+    owner: bool,
+}
+
+// The region is only touched through the atomic-synchronized `Ring`, so the
+// handle is safe to move to the producer/consumer thread.
+unsafe impl Send for SharedRegion {}
+
+impl SharedRegion {
+    /// Creates and maps a fresh shared-memory object of `len` bytes. The
+    /// returned region owns the object and unlinks it on drop.
+    pub fn create(name: &str, len: usize) -> io::Result<SharedRegion> {
+        let cname = CString::new(name).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        let fd = unsafe {
+            libc::shm_open(
+                cname.as_ptr(),
+                libc::O_CREAT | libc::O_EXCL | libc::O_RDWR,
+                (libc::S_IRUSR | libc::S_IWUSR) as libc::c_uint,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if unsafe { libc::ftruncate(fd, len as libc::off_t) } < 0 {
+            let err = io::Error::last_os_error();
+            unsafe {
+                libc::close(fd);
+                libc::shm_unlink(cname.as_ptr());
+            }
+            return Err(err);
+        }
+
+        Self::map(cname, fd, len, true)
+    }
+
+    /// Reopens and maps an existing shared-memory object of `len` bytes created
+    /// by the peer. The region does not own the object.
+    pub fn open(name: &str, len: usize) -> io::Result<SharedRegion> {
+        let cname = CString::new(name).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        let fd = unsafe { libc::shm_open(cname.as_ptr(), libc::O_RDWR, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Self::map(cname, fd, len, false)
+    }
+
+    fn map(name: CString, fd: libc::c_int, len: usize, owner: bool) -> io::Result<SharedRegion> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            let err = io::Error::last_os_error();
+            unsafe {
+                libc::close(fd);
+                if owner {
+                    libc::shm_unlink(name.as_ptr());
+                }
+            }
+            return Err(err);
+        }
+
+        Ok(SharedRegion {
+            ptr: ptr as *mut u8,
+            len,
+            fd,
+            name,
+            owner,
+        })
+    }
+
+    /// Builds a [`Ring`] over the mapped bytes. The producer and consumer each
+    /// build their own `Ring` over their respective mapping of the same object.
+    pub fn ring(&self) -> Ring {
+        unsafe { Ring::new(self.ptr, self.len) }
+    }
+
+    /// The shared-object name, so the peer can `open` the same region.
+    pub fn name(&self) -> &str {
+        self.name.to_str().unwrap_or_default()
+    }
+
+    /// Total size of the mapping in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl Drop for SharedRegion {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+            libc::close(self.fd);
+            // Only the creator unlinks, once, so a late consumer mapping stays
+            // valid until it too is unmapped.
+            if self.owner {
+                libc::shm_unlink(self.name.as_ptr());
+            }
+        }
+    }
+}
+
+/// Mints a process-unique shared-object name for a new session ring.
+pub fn unique_name() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("/chlorate-{}-{}", std::process::id(), n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a [`Ring`] over a plain heap buffer for testing, standing in for a
+    /// shared mapping. The buffer must outlive the returned `Ring`.
+    fn ring_over(region: &mut [u8]) -> Ring {
+        unsafe { Ring::new(region.as_mut_ptr(), region.len()) }
+    }
+
+    #[test]
+    fn push_pop_round_trips() {
+        let mut region = vec![0u8; HEADER + 8];
+        let ring = ring_over(&mut region);
+
+        assert_eq!(ring.push(&[1, 2, 3]), 3);
+        let mut out = [0u8; 3];
+        assert_eq!(ring.pop(&mut out), 3);
+        assert_eq!(out, [1, 2, 3]);
+    }
+
+    #[test]
+    fn push_stops_one_slot_short_of_full() {
+        // Capacity 8 bytes keeps one slot empty, so at most 7 bytes fit at once.
+        let mut region = vec![0u8; HEADER + 8];
+        let ring = ring_over(&mut region);
+
+        assert_eq!(ring.push(&[0; 10]), 7);
+        let mut out = [0u8; 10];
+        assert_eq!(ring.pop(&mut out), 7);
+    }
+
+    #[test]
+    fn push_pop_wraps_around() {
+        let mut region = vec![0u8; HEADER + 8];
+        let ring = ring_over(&mut region);
+
+        // Advance head/tail near the end of the data area.
+        assert_eq!(ring.push(&[1, 2, 3, 4, 5]), 5);
+        let mut out = [0u8; 5];
+        assert_eq!(ring.pop(&mut out), 5);
+
+        // This write straddles the end of the buffer and wraps to the front.
+        assert_eq!(ring.push(&[6, 7, 8, 9, 10, 11]), 6);
+        let mut out = [0u8; 6];
+        assert_eq!(ring.pop(&mut out), 6);
+        assert_eq!(out, [6, 7, 8, 9, 10, 11]);
+    }
+}
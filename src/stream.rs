@@ -0,0 +1,31 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc::UnboundedReceiver;
+use futures::Stream;
+
+use crate::SodaResponse;
+
+/// A [`Stream`] of decoded `SodaResponse`s produced by
+/// [`SodaBuilder::build_stream`](crate::SodaBuilder::build_stream).
+///
+/// The `extern "C"` callback pushes each decoded response into the sender half
+/// of an unbounded channel; this type wraps the receiver and yields responses
+/// as they arrive, ending once the owning `SodaClient` is dropped.
+pub struct SodaResponseStream {
+    inner: UnboundedReceiver<SodaResponse>,
+}
+
+impl SodaResponseStream {
+    pub(crate) fn new(inner: UnboundedReceiver<SodaResponse>) -> SodaResponseStream {
+        SodaResponseStream { inner }
+    }
+}
+
+impl Stream for SodaResponseStream {
+    type Item = SodaResponse;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<SodaResponse>> {
+        Pin::new(&mut self.get_mut().inner).poll_next(cx)
+    }
+}
@@ -0,0 +1,38 @@
+use std::io::Cursor;
+
+use chlorate::rpc::{read_frame, write_frame, RpcFrame, RpcPayload};
+
+#[test]
+fn frames_round_trip_and_eof_is_clean() {
+    // Three frames (config, audio and response) written back to back.
+    let mut buf = Vec::new();
+    write_frame(&mut buf, &RpcFrame::config(vec![1, 2, 3])).unwrap();
+    write_frame(&mut buf, &RpcFrame::audio(vec![4, 5])).unwrap();
+    write_frame(&mut buf, &RpcFrame::response(vec![6, 7, 8, 9])).unwrap();
+
+    let mut reader = Cursor::new(buf);
+
+    match read_frame(&mut reader).unwrap() {
+        Some(RpcFrame {
+            payload: Some(RpcPayload::Config(bytes)),
+        }) => assert_eq!(bytes, vec![1, 2, 3]),
+        _ => panic!("expected config frame"),
+    }
+
+    match read_frame(&mut reader).unwrap() {
+        Some(RpcFrame {
+            payload: Some(RpcPayload::Audio(bytes)),
+        }) => assert_eq!(bytes, vec![4, 5]),
+        _ => panic!("expected audio frame"),
+    }
+
+    match read_frame(&mut reader).unwrap() {
+        Some(RpcFrame {
+            payload: Some(RpcPayload::Response(bytes)),
+        }) => assert_eq!(bytes, vec![6, 7, 8, 9]),
+        _ => panic!("expected response frame"),
+    }
+
+    // A clean end of stream surfaces as `Ok(None)`, not an error.
+    assert!(read_frame(&mut reader).unwrap().is_none());
+}